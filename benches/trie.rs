@@ -0,0 +1,82 @@
+//! Timing benchmarks without external dependencies. Run with `cargo bench`.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use trie_again::{levenshtein_distance, Trie};
+
+const MEASUREMENT_TIME: Duration = Duration::from_millis(500);
+
+/// Runs `routine` on fresh input from `setup` for about `MEASUREMENT_TIME`,
+/// timing only `routine`, and prints and returns the mean time per run.
+fn bench<T>(name: &str, mut setup: impl FnMut() -> T, mut routine: impl FnMut(T)) -> Duration {
+    let mut total = Duration::ZERO;
+    let mut runs = 0;
+    while total < MEASUREMENT_TIME {
+        let input = setup();
+        let start = Instant::now();
+        routine(black_box(input));
+        total += start.elapsed();
+        runs += 1;
+    }
+    let mean = total / runs;
+    println!("{name:<40} {mean:>12.2?} ({runs} runs)");
+    mean
+}
+
+/// Deleting a word must stay linear in its length, so a word ten times as
+/// long may take about ten times as long, but nowhere near a hundred.
+fn bench_delete_long_word() {
+    let mut means = Vec::new();
+    for len in [1_000, 10_000] {
+        let word = "a".repeat(len);
+        let mean = bench(
+            &format!("delete a {len}-char word"),
+            || {
+                let mut trie = Trie::default();
+                trie.add(&word);
+                trie
+            },
+            |mut trie| trie.delete(&word).unwrap(),
+        );
+        means.push(mean);
+    }
+    let ratio = means[1].as_secs_f64() / means[0].as_secs_f64();
+    println!("{:<40} {ratio:>12.1}x", "delete scaling for 10x the length");
+    assert!(ratio < 30.0, "delete looks superlinear: {ratio:.1}x");
+}
+
+/// Common English words next to frequent misspellings or related words.
+const ENGLISH_PAIRS: [(&str, &str); 12] = [
+    ("kitten", "sitting"),
+    ("saturday", "sunday"),
+    ("receive", "recieve"),
+    ("separate", "seperate"),
+    ("definitely", "definately"),
+    ("necessary", "neccessary"),
+    ("accommodate", "acommodate"),
+    ("government", "goverment"),
+    ("environment", "enviroment"),
+    ("intention", "execution"),
+    ("the", "then"),
+    ("book", "back"),
+];
+
+fn bench_levenshtein_english_pairs() {
+    bench(
+        "levenshtein_distance on 12 English pairs",
+        || ENGLISH_PAIRS,
+        |pairs| {
+            for (a, b) in pairs {
+                black_box(levenshtein_distance(a, b));
+            }
+        },
+    );
+}
+
+fn main() {
+    bench_delete_long_word();
+    bench_levenshtein_english_pairs();
+}
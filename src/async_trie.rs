@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+
+use crate::{Trie, TrieError};
+
+/// A `Trie` shared between tasks behind a `tokio` read-write lock. Cloning
+/// yields another handle to the same trie.
+#[derive(Clone, Default)]
+pub struct AsyncTrie {
+    inner: Arc<RwLock<Trie>>,
+}
+
+impl AsyncTrie {
+    pub fn new(trie: Trie) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(trie)),
+        }
+    }
+}
+
+impl AsyncTrie {
+    /// Adds `word`, returning whether it was not stored before.
+    pub async fn add(&self, word: &str) -> bool {
+        let mut trie = self.inner.write().await;
+        let before = trie.count();
+        trie.add(word);
+        trie.count() > before
+    }
+
+    pub async fn search(&self, word: &str) -> bool {
+        self.inner.read().await.search(word)
+    }
+
+    pub async fn delete(&self, word: &str) -> Result<(), TrieError> {
+        self.inner.write().await.delete(word)
+    }
+
+    pub async fn count(&self) -> i32 {
+        self.inner.read().await.count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_add_search_delete() {
+        let trie = AsyncTrie::default();
+        assert!(trie.add("hello").await);
+        assert!(!trie.add("hello").await);
+        assert!(trie.search("hello").await);
+        assert!(!trie.search("hell").await);
+
+        trie.delete("hello").await.unwrap();
+        assert!(!trie.search("hello").await);
+        assert_eq!(
+            trie.delete("hello").await,
+            Err(TrieError::WordNotFound("hello".to_string()))
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_tasks() {
+        let trie = AsyncTrie::new("seed".parse().unwrap());
+        let mut handles = Vec::new();
+        for task in 0..8 {
+            let trie = trie.clone();
+            handles.push(tokio::spawn(async move {
+                for i in 0..50 {
+                    let word = format!("task{task}-{i}");
+                    assert!(trie.add(&word).await);
+                    assert!(trie.search(&word).await);
+                    assert!(trie.search("seed").await);
+                }
+            }));
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(trie.count().await, 8 * 50 + 1);
+    }
+}
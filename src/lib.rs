@@ -1,20 +1,165 @@
-use std::collections::{hash_map::Entry, HashMap};
+use std::{
+    any::Any,
+    cmp::Ordering,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    convert::Infallible,
+    error::Error,
+    fmt, io,
+    str::FromStr,
+};
+
+#[cfg(feature = "tokio")]
+mod async_trie;
+#[cfg(feature = "tokio")]
+pub use async_trie::AsyncTrie;
 
 #[derive(Default)]
 pub struct Node {
     children: HashMap<char, Node>,
     is_end: bool,
+    metadata: Option<Box<dyn Any + Send + Sync>>,
+}
+
+impl Node {
+    /// Copies this node and the nodes up to `max_depth` levels below it. Nodes
+    /// at `max_depth` keep their end flag but lose their children. Metadata is
+    /// not carried over, as it cannot be cloned.
+    pub fn depth_limited_clone(&self, max_depth: usize) -> Node {
+        let children = match max_depth {
+            0 => HashMap::new(),
+            _ => self
+                .children
+                .iter()
+                .map(|(&c, child)| (c, child.depth_limited_clone(max_depth - 1)))
+                .collect(),
+        };
+        Node {
+            children,
+            is_end: self.is_end,
+            metadata: None,
+        }
+    }
+
+    fn has_word(&self) -> bool {
+        self.is_end || self.children.values().any(Node::has_word)
+    }
+
+    fn estimate_word_count(&self, depth: usize) -> usize {
+        if depth == Trie::APPROXIMATION_DEPTH {
+            return self.probe_word_count();
+        }
+        usize::from(self.is_end)
+            + self
+                .children
+                .values()
+                .map(|child| child.estimate_word_count(depth + 1))
+                .sum::<usize>()
+    }
+
+    /// Follows the smallest child leading to a word until no word is left,
+    /// assuming each node met has as many equal siblings as its parent has
+    /// children leading to a word.
+    fn probe_word_count(&self) -> usize {
+        let mut estimate: usize = 0;
+        let mut siblings: usize = 1;
+        let mut node = self;
+        loop {
+            if node.is_end {
+                estimate = estimate.saturating_add(siblings);
+            }
+            let live: Vec<_> = node
+                .children
+                .iter()
+                .filter(|(_, child)| child.has_word())
+                .collect();
+            let Some(&(_, next)) = live.iter().min_by_key(|(&c, _)| c) else {
+                return estimate;
+            };
+            siblings = siblings.saturating_mul(live.len());
+            node = next;
+        }
+    }
+
+    fn word_count(&self) -> usize {
+        usize::from(self.is_end) + self.children.values().map(Node::word_count).sum::<usize>()
+    }
 }
 
 #[derive(Default)]
 pub struct Trie {
     root: Node,
     count: i32,
+    reject_empty: bool,
+    phonetic_index: Option<HashMap<String, Vec<String>>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TrieError {
-    WordNotFound,
+    WordNotFound(String),
+    EmptyWord,
+    PrefixIsWord(String),
+}
+
+impl fmt::Display for TrieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrieError::WordNotFound(word) => write!(f, "word '{word}' not found in trie"),
+            TrieError::EmptyWord => write!(f, "empty word is not accepted by this trie"),
+            TrieError::PrefixIsWord(word) => {
+                write!(f, "word '{word}' is a prefix of other stored words")
+            }
+        }
+    }
+}
+
+impl Error for TrieError {}
+
+impl From<TrieError> for String {
+    fn from(err: TrieError) -> Self {
+        err.to_string()
+    }
+}
+
+impl From<TrieError> for io::Error {
+    fn from(err: TrieError) -> Self {
+        let kind = match err {
+            TrieError::WordNotFound(_) => io::ErrorKind::NotFound,
+            TrieError::EmptyWord | TrieError::PrefixIsWord(_) => io::ErrorKind::InvalidInput,
+        };
+        io::Error::new(kind, err)
+    }
+}
+
+/// A read-only view of a node, for walking the trie by hand.
+#[derive(Clone, Copy)]
+pub struct TrieNode<'a> {
+    node: &'a Node,
+}
+
+impl<'a> TrieNode<'a> {
+    pub fn is_end(&self) -> bool {
+        self.node.is_end
+    }
+
+    pub fn get_child(&self, c: char) -> Option<TrieNode<'a>> {
+        self.node.children.get(&c).map(|node| TrieNode { node })
+    }
+
+    /// Iterates over the outgoing edges in arbitrary order.
+    pub fn children(&self) -> impl Iterator<Item = (char, TrieNode<'a>)> + 'a {
+        self.node
+            .children
+            .iter()
+            .map(|(&c, node)| (c, TrieNode { node }))
+    }
+}
+
+impl Trie {
+    /// Creates an empty trie holding no words, so `count()` starts at zero.
+    /// Same as [`Trie::default`].
+    pub fn new() -> Self {
+        Self::default()
+    }
 }
 
 impl Trie {
@@ -23,151 +168,2700 @@ impl Trie {
     }
 }
 
+impl Trie {
+    /// Whether `""` can be stored. Enabled by default; when disabled, `add("")`
+    /// is a no-op, `search("")` is `false` and `delete("")` fails with
+    /// [`TrieError::EmptyWord`].
+    pub fn accepts_empty_string(&self) -> bool {
+        !self.reject_empty
+    }
+
+    /// Disallowing the empty string removes it if it is currently stored.
+    pub fn set_accepts_empty_string(&mut self, accepts: bool) {
+        self.reject_empty = !accepts;
+        if self.reject_empty && self.root.is_end {
+            self.root.is_end = false;
+            self.root.metadata = None;
+            self.count -= 1;
+        }
+    }
+}
+
 impl Trie {
     pub fn add(&mut self, word: &str) {
+        if self.reject_empty && word.is_empty() {
+            return;
+        }
         let mut current = &mut self.root;
         for c in word.chars() {
-            current = current.children.entry(c).or_insert(Node {
-                children: HashMap::new(),
-                is_end: false,
-            })
+            current = current.children.entry(c).or_default();
+        }
+        if !current.is_end {
+            current.is_end = true;
+            self.count += 1;
+            self.index_phonetic(word);
         }
-        current.is_end = true;
-        self.count += 1;
     }
 }
 
 impl Trie {
-    pub fn search(&self, word: &str) -> bool {
+    fn node_at_prefix(&self, prefix: &str) -> Option<&Node> {
         let mut current = &self.root;
-        for c in word.chars() {
-            if let Some(found) = current.children.get(&c) {
-                current = found;
-                continue;
-            }
-            return false;
+        for c in prefix.chars() {
+            current = current.children.get(&c)?;
+        }
+        Some(current)
+    }
+
+    fn node_at_prefix_mut(&mut self, prefix: &str) -> Option<&mut Node> {
+        let mut current = &mut self.root;
+        for c in prefix.chars() {
+            current = current.children.get_mut(&c)?;
         }
-        current.is_end
+        Some(current)
+    }
+}
+
+impl Trie {
+    pub fn search(&self, word: &str) -> bool {
+        self.node_at_prefix(word).is_some_and(|node| node.is_end)
     }
 }
 
 impl Trie {
-    pub fn get_child<'a>(&'a self, ch: char, node: &'a Node) -> Option<&Node> {
+    pub fn contains_all(&self, words: &[&str]) -> bool {
+        words.iter().all(|word| self.search(word))
+    }
+}
+
+impl Trie {
+    pub fn get_child<'a>(&'a self, ch: char, node: &'a Node) -> Option<&'a Node> {
         node.children.get(&ch)
     }
 }
 
 impl Trie {
     pub fn delete(&mut self, word: &str) -> Result<(), TrieError> {
-        fn delete_recursive(node: &mut Node, word: &str, index: usize) -> Result<bool, TrieError> {
-            if index == word.len() {
+        fn delete_recursive(node: &mut Node, word: &[char]) -> Option<bool> {
+            let Some((&c, rest)) = word.split_first() else {
                 if !node.is_end {
-                    return Err(TrieError::WordNotFound);
+                    return None;
                 }
                 node.is_end = false;
-                return Ok(node.children.is_empty());
-            }
-            let c = word.chars().nth(index).unwrap();
+                node.metadata = None;
+                return Some(!node.children.values().any(Node::has_word));
+            };
             match node.children.entry(c) {
                 Entry::Occupied(mut entry) => {
                     let next_node = entry.get_mut();
-                    let should_delete = delete_recursive(next_node, word, index + 1)?;
+                    let should_delete = delete_recursive(next_node, rest)?;
                     if should_delete {
                         entry.remove_entry();
-                        return Ok(node.children.is_empty() && !node.is_end);
+                        return Some(!node.is_end && !node.children.values().any(Node::has_word));
                     };
                 }
-                Entry::Vacant(_) => return Err(TrieError::WordNotFound),
+                Entry::Vacant(_) => return None,
             }
-            Ok(false)
+            Some(false)
         }
-        let result = delete_recursive(&mut self.root, word, 0);
-        if result.is_ok() {
-            self.count -= 1;
-            Ok(())
-        } else {
-            Err(result.unwrap_err())
+        if self.reject_empty && word.is_empty() {
+            return Err(TrieError::EmptyWord);
         }
+        let chars: Vec<char> = word.chars().collect();
+        delete_recursive(&mut self.root, &chars)
+            .ok_or_else(|| TrieError::WordNotFound(word.to_string()))?;
+        self.count -= 1;
+        self.unindex_phonetic(word);
+        Ok(())
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl Trie {
+    pub fn max_branching_factor(&self) -> usize {
+        self.branching_stats().0
+    }
 
-    #[test]
-    fn test_add_and_search() {
-        let mut trie = Trie::default();
-        trie.add("hello");
-        trie.add("hey");
-        trie.add("hi");
+    pub fn average_branching_factor(&self) -> f64 {
+        let (_, children, inner_nodes) = self.branching_stats();
+        if inner_nodes == 0 {
+            return 0.0;
+        }
+        children as f64 / inner_nodes as f64
+    }
 
-        assert!(trie.search("hello"));
-        assert!(trie.search("hey"));
-        assert!(trie.search("hi"));
-        assert!(!trie.search("test"));
-        assert!(!trie.search("he"));
+    fn branching_stats(&self) -> (usize, usize, usize) {
+        fn stats_recursive(node: &Node, stats: &mut (usize, usize, usize)) {
+            if node.children.is_empty() {
+                return;
+            }
+            stats.0 = stats.0.max(node.children.len());
+            stats.1 += node.children.len();
+            stats.2 += 1;
+            for child in node.children.values() {
+                stats_recursive(child, stats);
+            }
+        }
+        let mut stats = (0, 0, 0);
+        stats_recursive(&self.root, &mut stats);
+        stats
     }
+}
 
-    #[test]
-    fn test_get_child() {
-        let mut trie = Trie::default();
-        trie.add("hello");
-        trie.add("hey");
-        trie.add("hi");
+impl Trie {
+    /// Exchanges the contents of two tries in O(1).
+    ///
+    /// Handy for double-buffering: build a replacement trie while the old one
+    /// keeps serving searches, then swap it in.
+    pub fn swap(&mut self, other: &mut Trie) {
+        std::mem::swap(&mut self.root, &mut other.root);
+        std::mem::swap(&mut self.count, &mut other.count);
+        std::mem::swap(&mut self.reject_empty, &mut other.reject_empty);
+        std::mem::swap(&mut self.phonetic_index, &mut other.phonetic_index);
+    }
+}
 
-        let mut found = trie.get_child('h', &trie.root);
-        assert!(found.is_some());
-        found = trie.get_child('e', found.unwrap());
-        assert!(found.is_some());
-        found = trie.get_child('x', found.unwrap());
-        assert!(found.is_none());
+impl Trie {
+    /// Returns the number of edges between the root and the end of `word`,
+    /// which is its length in `char`s rather than bytes.
+    pub fn depth_at(&self, word: &str) -> Option<usize> {
+        let node = self.node_at_prefix(word)?;
+        node.is_end.then(|| word.chars().count())
     }
+}
 
-    #[test]
-    fn test_delete() {
-        let mut trie = Trie::default();
-        trie.add("hello");
-        trie.add("hey");
-        trie.add("hi");
+impl Trie {
+    /// Returns every character that ends at least one stored word. The empty
+    /// string has no final character, so storing it contributes nothing.
+    pub fn unique_terminal_chars(&self) -> HashSet<char> {
+        fn collect_recursive(node: &Node, chars: &mut HashSet<char>) {
+            for (&c, child) in &node.children {
+                if child.is_end {
+                    chars.insert(c);
+                }
+                collect_recursive(child, chars);
+            }
+        }
+        let mut chars = HashSet::new();
+        collect_recursive(&self.root, &mut chars);
+        chars
+    }
+}
 
-        assert!(trie.search("hello"));
-        trie.delete("hello").unwrap();
-        assert!(!trie.search("hello"));
+impl Trie {
+    fn nodes_iter(&self) -> impl Iterator<Item = (String, &Node)> + '_ {
+        let mut stack = vec![(String::new(), &self.root)];
+        std::iter::from_fn(move || {
+            let (path, node) = stack.pop()?;
+            let mut children: Vec<_> = node.children.iter().collect();
+            children.sort_unstable_by(|a, b| b.0.cmp(a.0));
+            for (&c, child) in children {
+                let mut child_path = path.clone();
+                child_path.push(c);
+                stack.push((child_path, child));
+            }
+            Some((path, node))
+        })
+    }
 
-        assert!(trie.search("hey"));
-        trie.delete("hey").unwrap();
-        assert!(!trie.search("hey"));
+    /// Iterates over every node, root included, yielding the path leading to
+    /// it and whether a word ends there. Nodes come in lexicographic order of
+    /// their paths.
+    pub fn iter_all_nodes(&self) -> impl Iterator<Item = (String, bool)> + '_ {
+        self.nodes_iter().map(|(path, node)| (path, node.is_end))
+    }
+}
 
-        assert!(trie.search("hi"));
-        trie.delete("hi").unwrap();
-        assert!(!trie.search("hi"));
+impl Trie {
+    /// Iterates over all stored words in lexicographic order.
+    pub fn words_iter(&self) -> impl Iterator<Item = String> + '_ {
+        self.iter_all_nodes()
+            .filter_map(|(path, is_end)| is_end.then_some(path))
+    }
+}
 
-        assert_eq!(trie.count(), 0);
+impl Trie {
+    /// Returns the number of nodes below the root.
+    pub fn total_node_count(&self) -> usize {
+        fn count_recursive(node: &Node) -> usize {
+            node.children
+                .values()
+                .map(|child| 1 + count_recursive(child))
+                .sum()
+        }
+        count_recursive(&self.root)
     }
+}
 
-    #[test]
-    fn test_delete_non_existing() {
-        let mut trie = Trie::default();
-        trie.add("hello");
+impl Trie {
+    /// Returns the ratio of nodes to the total characters of all stored words.
+    /// `1.0` means no prefix is shared at all; lower values mean more sharing.
+    pub fn prefix_compression_ratio(&self) -> f64 {
+        let total_chars: usize = self.words_iter().map(|w| w.chars().count()).sum();
+        if total_chars == 0 {
+            return 0.0;
+        }
+        self.total_node_count() as f64 / total_chars as f64
+    }
+}
 
-        let result = trie.delete("test");
-        assert!(result.is_err());
-        match result.unwrap_err() {
-            TrieError::WordNotFound => (),
+impl Trie {
+    /// Inserts every word of `other` into `self` with `prefix` prepended.
+    pub fn subtrie_merge(&mut self, prefix: &str, other: Trie) {
+        let mut word = String::from(prefix);
+        for suffix in other.words_iter() {
+            word.truncate(prefix.len());
+            word.push_str(&suffix);
+            self.add(&word);
         }
     }
+}
 
-    #[test]
-    fn test_delete_prefix() {
+impl Trie {
+    /// Re-roots `trie` at the node reached by `prefix`. The prefix is consumed,
+    /// so `"apple"` becomes `"le"` for the prefix `"app"`. Yields an empty trie
+    /// if no stored word starts with `prefix`.
+    pub fn from_trie(trie: Trie, prefix: &str) -> Trie {
+        let mut root = trie.root;
+        for c in prefix.chars() {
+            match root.children.remove(&c) {
+                Some(child) => root = child,
+                None => {
+                    root = Node::default();
+                    break;
+                }
+            }
+        }
+        if trie.reject_empty {
+            root.is_end = false;
+            root.metadata = None;
+        }
+        let mut sub = Trie {
+            count: root.word_count() as i32,
+            root,
+            reject_empty: trie.reject_empty,
+            phonetic_index: None,
+        };
+        sub.set_phonetic_index(trie.phonetic_index.is_some());
+        sub
+    }
+}
+
+impl Trie {
+    /// Counts the stored words that are strict prefixes of `query`.
+    pub fn count_exact_prefix_matches(&self, query: &str) -> usize {
+        let mut current = &self.root;
+        let mut matches = 0;
+        for c in query.chars() {
+            if current.is_end {
+                matches += 1;
+            }
+            match current.children.get(&c) {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        matches
+    }
+}
+
+impl Trie {
+    /// Returns the longest stored word that is not a suffix of any other stored
+    /// word, preferring the lexicographically smallest on ties.
+    ///
+    /// Rather than comparing all pairs of words, which is O(words²), this builds
+    /// a trie of the reversed words: a word is a suffix of another exactly when
+    /// its reversed path has children there. That keeps the whole search linear
+    /// in the total number of characters, at the cost of a second trie.
+    pub fn longest_suffix_free_word(&self) -> Option<String> {
+        let mut reversed = Trie::default();
+        for word in self.words_iter() {
+            reversed.add(&word.chars().rev().collect::<String>());
+        }
+        let mut longest: Option<(usize, String)> = None;
+        for word in self.words_iter() {
+            let mut current = &reversed.root;
+            for c in word.chars().rev() {
+                current = &current.children[&c];
+            }
+            let len = word.chars().count();
+            if current.children.is_empty() && longest.as_ref().is_none_or(|(l, _)| len > *l) {
+                longest = Some((len, word));
+            }
+        }
+        longest.map(|(_, word)| word)
+    }
+}
+
+impl Trie {
+    /// Removes and returns a stored word. Which word is unspecified, though it
+    /// currently is the lexicographically smallest one.
+    pub fn pop_word(&mut self) -> Option<String> {
+        let word = self.words_iter().next()?;
+        self.delete(&word).ok()?;
+        Some(word)
+    }
+}
+
+impl Trie {
+    /// Counts the words stored in both tries by walking them side by side,
+    /// without building an intermediate trie.
+    pub fn intersection_count(&self, other: &Trie) -> usize {
+        fn count_recursive(a: &Node, b: &Node) -> usize {
+            let (small, large) = if a.children.len() <= b.children.len() {
+                (a, b)
+            } else {
+                (b, a)
+            };
+            let shared: usize = small
+                .children
+                .iter()
+                .filter_map(|(c, child)| Some(count_recursive(child, large.children.get(c)?)))
+                .sum();
+            usize::from(a.is_end && b.is_end) + shared
+        }
+        count_recursive(&self.root, &other.root)
+    }
+
+    pub fn is_subset_of(&self, other: &Trie) -> bool {
+        self.intersection_count(other) == self.count as usize
+    }
+}
+
+impl Trie {
+    /// Returns the words of `self` missing from `other`, sorted.
+    pub fn words_not_in(&self, other: &Trie) -> Vec<String> {
+        self.words_iter().filter(|w| !other.search(w)).collect()
+    }
+
+    /// Returns the words stored in both `self` and `other`, sorted.
+    pub fn words_also_in(&self, other: &Trie) -> Vec<String> {
+        self.words_iter().filter(|w| other.search(w)).collect()
+    }
+}
+
+impl Trie {
+    pub fn root_node(&self) -> TrieNode<'_> {
+        TrieNode { node: &self.root }
+    }
+}
+
+/// Writes the sorted words one per line, with no trailing line break. Line
+/// breaks and backslashes inside words are escaped as `\n`, `\r` and `\\`.
+/// The empty word is an empty line, so a trie holding only `""` is written as
+/// a lone `"\n"` to tell it apart from an empty trie.
+impl fmt::Display for Trie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.count == 1 && self.root.is_end {
+            return writeln!(f);
+        }
+        for (i, word) in self.words_iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for c in word.chars() {
+                match c {
+                    '\\' => f.write_str("\\\\")?,
+                    '\n' => f.write_str("\\n")?,
+                    '\r' => f.write_str("\\r")?,
+                    c => write!(f, "{c}")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn unescape_line(line: &str) -> String {
+    let mut word = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            word.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => word.push('\n'),
+            Some('r') => word.push('\r'),
+            Some('\\') => word.push('\\'),
+            // Not an escape written by `Display`; keep it as is.
+            Some(other) => {
+                word.push('\\');
+                word.push(other);
+            }
+            None => word.push('\\'),
+        }
+    }
+    word
+}
+
+impl FromStr for Trie {
+    type Err = Infallible;
+
+    /// Parses one word per line, the format written by `Display`, undoing its
+    /// escapes. A final line break is ignored, and so is a `\r` before any
+    /// line break.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut trie = Trie::default();
-        trie.add("hello");
-        trie.add("hey");
+        for line in s.lines() {
+            trie.add(&unescape_line(line));
+        }
+        Ok(trie)
+    }
+}
 
-        let result = trie.delete("he");
-        assert!(result.is_err());
-        match result.unwrap_err() {
-            TrieError::WordNotFound => (),
+/// Returns the edit distance between `a` and `b`, counted in `char`s.
+pub fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+fn soundex(word: &str) -> String {
+    fn digit(c: char) -> Option<char> {
+        match c {
+            'b' | 'f' | 'p' | 'v' => Some('1'),
+            'c' | 'g' | 'j' | 'k' | 'q' | 's' | 'x' | 'z' => Some('2'),
+            'd' | 't' => Some('3'),
+            'l' => Some('4'),
+            'm' | 'n' => Some('5'),
+            'r' => Some('6'),
+            _ => None,
+        }
+    }
+    let mut letters = word
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_lowercase());
+    let Some(first) = letters.next() else {
+        return String::new();
+    };
+    let mut code = String::from(first.to_ascii_uppercase());
+    let mut last = digit(first);
+    for c in letters {
+        if code.len() == 4 {
+            break;
+        }
+        let d = digit(c);
+        if d.is_some() && d != last {
+            code.extend(d);
+        }
+        // 'h' and 'w' do not separate consonants with the same code.
+        if c != 'h' && c != 'w' {
+            last = d;
+        }
+    }
+    while code.len() < 4 {
+        code.push('0');
+    }
+    code
+}
+
+impl Trie {
+    /// Keeps a Soundex index up to date on every insert and delete, so that
+    /// `phonetic_search` is a lookup instead of a scan. Off by default.
+    pub fn set_phonetic_index(&mut self, enabled: bool) {
+        if !enabled {
+            self.phonetic_index = None;
+            return;
+        }
+        if self.phonetic_index.is_some() {
+            return;
+        }
+        let mut index: HashMap<String, Vec<String>> = HashMap::new();
+        for word in self.words_iter() {
+            let code = soundex(&word);
+            if !code.is_empty() {
+                index.entry(code).or_default().push(word);
+            }
         }
+        self.phonetic_index = Some(index);
+    }
+
+    fn index_phonetic(&mut self, word: &str) {
+        if let Some(index) = &mut self.phonetic_index {
+            let code = soundex(word);
+            if !code.is_empty() {
+                index.entry(code).or_default().push(word.to_string());
+            }
+        }
+    }
+
+    fn unindex_phonetic(&mut self, word: &str) {
+        if let Some(index) = &mut self.phonetic_index {
+            if let Entry::Occupied(mut entry) = index.entry(soundex(word)) {
+                entry.get_mut().retain(|w| w != word);
+                if entry.get().is_empty() {
+                    entry.remove();
+                }
+            }
+        }
+    }
+
+    /// Returns the stored words sharing the Soundex code of `word`, sorted.
+    /// Only ASCII letters are encoded; words without any match nothing.
+    pub fn phonetic_search(&self, word: &str) -> Vec<String> {
+        let code = soundex(word);
+        if code.is_empty() {
+            return Vec::new();
+        }
+        match &self.phonetic_index {
+            Some(index) => {
+                let mut words = index.get(&code).cloned().unwrap_or_default();
+                words.sort();
+                words
+            }
+            None => self.words_iter().filter(|w| soundex(w) == code).collect(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct FrequencyNode {
+    children: HashMap<char, FrequencyNode>,
+    frequency: usize,
+}
+
+/// A trie that counts how many times each word was added.
+#[derive(Default)]
+pub struct FrequencyTrie {
+    root: FrequencyNode,
+    count: usize,
+    node_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+impl FrequencyTrie {
+    /// Returns the number of distinct words.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn distinct_word_count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the number of nodes below the root.
+    pub fn total_node_count(&self) -> usize {
+        self.node_count
+    }
+}
+
+impl FrequencyTrie {
+    pub fn add(&mut self, word: &str) {
+        self.increment(word);
+    }
+
+    /// Counts one more occurrence of `word` and returns its new frequency, so
+    /// `1` means this is the first time it was seen.
+    pub fn increment(&mut self, word: &str) -> usize {
+        self.add_frequency(word, 1)
+    }
+
+    fn add_frequency(&mut self, word: &str, frequency: usize) -> usize {
+        if frequency == 0 {
+            return self.frequency(word);
+        }
+        let mut current = &mut self.root;
+        for c in word.chars() {
+            current = match current.children.entry(c) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    self.node_count += 1;
+                    entry.insert(FrequencyNode::default())
+                }
+            };
+        }
+        if current.frequency == 0 {
+            self.count += 1;
+        }
+        current.frequency += frequency;
+        current.frequency
+    }
+}
+
+impl FrequencyTrie {
+    /// Returns how many times `word` was added, `0` if never.
+    pub fn frequency(&self, word: &str) -> usize {
+        let mut current = &self.root;
+        for c in word.chars() {
+            match current.children.get(&c) {
+                Some(next) => current = next,
+                None => return 0,
+            }
+        }
+        current.frequency
+    }
+}
+
+impl FrequencyTrie {
+    fn entries(&self) -> Vec<(String, usize)> {
+        fn collect_recursive(
+            node: &FrequencyNode,
+            path: &mut String,
+            out: &mut Vec<(String, usize)>,
+        ) {
+            if node.frequency > 0 {
+                out.push((path.clone(), node.frequency));
+            }
+            for (&c, child) in &node.children {
+                path.push(c);
+                collect_recursive(child, path, out);
+                path.pop();
+            }
+        }
+        let mut entries = Vec::with_capacity(self.count);
+        collect_recursive(&self.root, &mut String::new(), &mut entries);
+        entries
+    }
+}
+
+impl FrequencyTrie {
+    pub fn word_frequency_map(&self) -> HashMap<String, usize> {
+        self.entries().into_iter().collect()
+    }
+
+    /// Builds a trie with each word's frequency set directly from `map`.
+    /// Words mapped to `0` are skipped.
+    pub fn from_frequency_map(map: HashMap<String, usize>) -> FrequencyTrie {
+        let mut trie = FrequencyTrie::default();
+        for (word, frequency) in map {
+            trie.add_frequency(&word, frequency);
+        }
+        trie
+    }
+}
+
+impl FrequencyTrie {
+    /// Adds the frequencies of `other` to those of `self`.
+    pub fn merge_frequency(&mut self, other: FrequencyTrie) {
+        for (word, frequency) in other.entries() {
+            self.add_frequency(&word, frequency);
+        }
+    }
+
+    /// Like `merge_frequency`, but leaves both inputs untouched.
+    pub fn merged_with(&self, other: &FrequencyTrie) -> FrequencyTrie {
+        let mut merged = FrequencyTrie::default();
+        for (word, frequency) in self.entries().into_iter().chain(other.entries()) {
+            merged.add_frequency(&word, frequency);
+        }
+        merged
+    }
+}
+
+impl FrequencyTrie {
+    /// Yields `(word, frequency)` pairs ordered by frequency, breaking ties
+    /// lexicographically. Sorts every entry up front, so this is O(n log n).
+    pub fn iter_by_frequency(&self, order: SortOrder) -> impl Iterator<Item = (String, usize)> {
+        let mut entries = self.entries();
+        entries.sort_unstable_by(|(a_word, a_freq), (b_word, b_freq)| {
+            let by_frequency = match order {
+                SortOrder::Ascending => a_freq.cmp(b_freq),
+                SortOrder::Descending => b_freq.cmp(a_freq),
+            };
+            by_frequency.then_with(|| a_word.cmp(b_word))
+        });
+        entries.into_iter()
+    }
+}
+
+/// Tries compare by their sorted word lists, the same way `Vec<String>`
+/// does. Comparison walks both tries, so it is O(n) in the total number of
+/// words rather than O(1).
+impl Ord for Trie {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.words_iter().cmp(other.words_iter())
+    }
+}
+
+impl PartialOrd for Trie {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Trie {
+    fn eq(&self, other: &Self) -> bool {
+        self.count == other.count && self.words_iter().eq(other.words_iter())
+    }
+}
+
+impl Eq for Trie {}
+
+/// Builds a trie from `(prefix, name)` pairs, storing `prefix` followed by
+/// `name` for each, e.g. `("user:", "alice")` becomes `"user:alice"`.
+pub fn trie_from_str_pairs(pairs: &[(&str, &str)]) -> Trie {
+    trie_from_segments(pairs.iter().map(|&(prefix, name)| [prefix, name]))
+}
+
+/// Builds a trie storing, for each item, the concatenation of its segments.
+pub fn trie_from_segments<'a>(
+    segments: impl IntoIterator<Item = impl IntoIterator<Item = &'a str>>,
+) -> Trie {
+    let mut trie = Trie::default();
+    let mut word = String::new();
+    for parts in segments {
+        word.clear();
+        word.extend(parts);
+        trie.add(&word);
+    }
+    trie
+}
+
+impl Trie {
+    /// Returns whether the path for `prefix` exists, along with the number of
+    /// stored words starting with it.
+    pub fn search_prefix_with_count(&self, prefix: &str) -> (bool, usize) {
+        match self.node_at_prefix(prefix) {
+            Some(node) => (true, node.word_count()),
+            None => (false, 0),
+        }
+    }
+}
+
+impl Trie {
+    /// Like `delete`, but refuses with [`TrieError::PrefixIsWord`] to remove a
+    /// word that other stored words start with.
+    pub fn delete_strict(&mut self, word: &str) -> Result<(), TrieError> {
+        let is_prefix = self
+            .node_at_prefix(word)
+            .is_some_and(|node| node.is_end && node.children.values().any(Node::has_word));
+        if is_prefix {
+            return Err(TrieError::PrefixIsWord(word.to_string()));
+        }
+        self.delete(word)
+    }
+}
+
+impl Trie {
+    /// Returns the number of child slots allocated across all nodes. This is
+    /// never less than `total_node_count()`; the difference is unused capacity.
+    pub fn capacity_hint(&self) -> usize {
+        fn capacity_recursive(node: &Node) -> usize {
+            node.children.capacity()
+                + node
+                    .children
+                    .values()
+                    .map(capacity_recursive)
+                    .sum::<usize>()
+        }
+        capacity_recursive(&self.root)
+    }
+}
+
+impl Trie {
+    const APPROXIMATION_DEPTH: usize = 3;
+
+    /// Estimates the number of stored words starting with `prefix` without
+    /// walking the whole subtrie. The first three levels below `prefix` are
+    /// counted exactly. Below each node of the third level, only one path is
+    /// walked, and the words on it are extrapolated to the whole level: every
+    /// word counts once for each node the level is expected to hold, taken as
+    /// the product of the branching factors met on the way down, as in Knuth's
+    /// estimate of search tree sizes.
+    ///
+    /// No `max_error_ratio` holds for every trie, as a probed branch much
+    /// larger or smaller than its siblings skews its whole level by that much.
+    /// The estimate is exact when no matching word is more than three
+    /// characters longer than `prefix`, or when the nodes at each depth below a
+    /// probed node all have as many children and the same end flag. It never
+    /// falls below the number of matching words of at most three more
+    /// characters plus one for each longer branch, so it is `0` only when no
+    /// word starts with `prefix`. On evenly spread words it usually stays
+    /// within 10% of `search_prefix_with_count`.
+    pub fn approximate_word_count_with_prefix(&self, prefix: &str) -> usize {
+        self.node_at_prefix(prefix)
+            .map_or(0, |node| node.estimate_word_count(0))
+    }
+}
+
+impl Trie {
+    /// Adds `word` with `meta` attached, returning the metadata it replaced if
+    /// that was also an `M`. Each word may carry metadata of a different type.
+    pub fn insert_with_metadata<M: Any + Send + Sync>(&mut self, word: &str, meta: M) -> Option<M> {
+        self.add(word);
+        let node = self.node_at_prefix_mut(word).filter(|node| node.is_end)?;
+        let previous = node.metadata.replace(Box::new(meta))?;
+        previous.downcast().ok().map(|previous| *previous)
+    }
+
+    /// Returns the metadata attached to `word`, or `None` if the word is not
+    /// stored or its metadata is not an `M`.
+    pub fn get_metadata<M: Any>(&self, word: &str) -> Option<&M> {
+        let node = self.node_at_prefix(word).filter(|node| node.is_end)?;
+        node.metadata.as_ref()?.downcast_ref()
+    }
+
+    /// Replaces the metadata of `word` with `f` applied to the current value,
+    /// adding the word first if it is missing. `f` receives `None` when there is
+    /// no metadata of type `M` yet.
+    pub fn update_metadata<M: Any + Send + Sync>(
+        &mut self,
+        word: &str,
+        f: impl FnOnce(Option<M>) -> M,
+    ) {
+        let current = self
+            .node_at_prefix_mut(word)
+            .filter(|node| node.is_end)
+            .and_then(|node| node.metadata.take())
+            .and_then(|meta| meta.downcast().ok())
+            .map(|meta| *meta);
+        self.insert_with_metadata(word, f(current));
+    }
+}
+
+/// What a trie knows about a queried word, as returned by
+/// [`Trie::search_with_prefix_info`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchOutcome {
+    NotInTrie,
+    /// Not stored, but other stored words start with it.
+    PrefixOnly {
+        completions_estimate: usize,
+    },
+    ExactMatch,
+    /// Stored, and other stored words start with it as well.
+    ExactMatchAndPrefix {
+        completions_estimate: usize,
+    },
+}
+
+impl Trie {
+    /// Combines `search` with a prefix check in a single walk down to `word`.
+    /// The number of completions excludes `word` itself and is estimated the
+    /// same way as in `approximate_word_count_with_prefix`.
+    pub fn search_with_prefix_info(&self, word: &str) -> SearchOutcome {
+        let Some(node) = self.node_at_prefix(word) else {
+            return SearchOutcome::NotInTrie;
+        };
+        let has_completions = node.children.values().any(Node::has_word);
+        if !has_completions {
+            return if node.is_end {
+                SearchOutcome::ExactMatch
+            } else {
+                SearchOutcome::NotInTrie
+            };
+        }
+        let completions_estimate = node.estimate_word_count(0) - usize::from(node.is_end);
+        if node.is_end {
+            SearchOutcome::ExactMatchAndPrefix {
+                completions_estimate,
+            }
+        } else {
+            SearchOutcome::PrefixOnly {
+                completions_estimate,
+            }
+        }
+    }
+}
+
+impl Trie {
+    /// Returns the sorted words made of exactly the characters in `chars`,
+    /// each used at least once. Branches leaving the set are never entered.
+    pub fn words_with_exact_char_set(&self, chars: &HashSet<char>) -> Vec<String> {
+        fn collect_recursive(
+            node: &Node,
+            chars: &HashSet<char>,
+            path: &mut String,
+            used: &mut HashMap<char, usize>,
+            words: &mut Vec<String>,
+        ) {
+            if node.is_end && used.len() == chars.len() {
+                words.push(path.clone());
+            }
+            for (&c, child) in &node.children {
+                if !chars.contains(&c) {
+                    continue;
+                }
+                path.push(c);
+                *used.entry(c).or_default() += 1;
+                collect_recursive(child, chars, path, used, words);
+                path.pop();
+                if let Entry::Occupied(mut entry) = used.entry(c) {
+                    *entry.get_mut() -= 1;
+                    if *entry.get() == 0 {
+                        entry.remove();
+                    }
+                }
+            }
+        }
+        let mut words = Vec::new();
+        collect_recursive(
+            &self.root,
+            chars,
+            &mut String::new(),
+            &mut HashMap::new(),
+            &mut words,
+        );
+        words.sort();
+        words
+    }
+}
+
+impl Trie {
+    /// Returns the sorted stored words using exactly the letters of `letters`,
+    /// `letters` itself included if stored. Branches are dropped as soon as
+    /// they need a letter that is used up.
+    pub fn anagram_search(&self, letters: &str) -> Vec<String> {
+        fn collect_recursive(
+            node: &Node,
+            remaining: usize,
+            budget: &mut HashMap<char, usize>,
+            path: &mut String,
+            words: &mut Vec<String>,
+        ) {
+            if remaining == 0 {
+                if node.is_end {
+                    words.push(path.clone());
+                }
+                return;
+            }
+            for (&c, child) in &node.children {
+                let Some(left) = budget.get_mut(&c).filter(|left| **left > 0) else {
+                    continue;
+                };
+                *left -= 1;
+                path.push(c);
+                collect_recursive(child, remaining - 1, budget, path, words);
+                path.pop();
+                *budget.get_mut(&c).unwrap() += 1;
+            }
+        }
+        let mut budget: HashMap<char, usize> = HashMap::new();
+        for c in letters.chars() {
+            *budget.entry(c).or_default() += 1;
+        }
+        let mut words = Vec::new();
+        let remaining = letters.chars().count();
+        collect_recursive(
+            &self.root,
+            remaining,
+            &mut budget,
+            &mut String::new(),
+            &mut words,
+        );
+        words.sort();
+        words
+    }
+}
+
+impl Trie {
+    /// Deletes every word missing from `other`, keeping only the intersection.
+    pub fn remove_words_not_in(&mut self, other: &Trie) {
+        for word in self.words_not_in(other) {
+            let _ = self.delete(&word);
+        }
+    }
+}
+
+impl Trie {
+    /// Returns the share of `self`'s nodes whose path also exists in `other`,
+    /// from `0.0` (no common structure) to `1.0` (all of it). An empty `self`
+    /// yields `0.0`.
+    pub fn prefix_overlap_ratio(&self, other: &Trie) -> f64 {
+        fn shared_recursive(a: &Node, b: &Node) -> usize {
+            a.children
+                .iter()
+                .filter_map(|(c, child)| Some(1 + shared_recursive(child, b.children.get(c)?)))
+                .sum()
+        }
+        let total = self.total_node_count();
+        if total == 0 {
+            return 0.0;
+        }
+        shared_recursive(&self.root, &other.root) as f64 / total as f64
+    }
+}
+
+impl Trie {
+    /// Deletes each word independently, returning how many were removed and
+    /// which ones could not be.
+    pub fn batch_delete(
+        &mut self,
+        words: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> (usize, Vec<String>) {
+        let mut deleted = 0;
+        let mut failed = Vec::new();
+        for word in words {
+            let word = word.as_ref();
+            match self.delete(word) {
+                Ok(()) => deleted += 1,
+                Err(_) => failed.push(word.to_string()),
+            }
+        }
+        (deleted, failed)
+    }
+}
+
+impl Trie {
+    /// Returns a copy holding only the words of at most `depth` characters.
+    ///
+    /// Paths of longer words are kept up to `depth` as a preview of the
+    /// structure, even though they no longer lead to a word. Only the
+    /// structural views see them: `path_exists`, `root_node`, `iter_all_nodes`,
+    /// `to_edge_list` and the node statistics. Every word-aware method treats
+    /// them as absent, and `delete` drops those along the deleted word.
+    pub fn shallow_clone(&self, depth: usize) -> Trie {
+        let root = self.root.depth_limited_clone(depth);
+        let mut clone = Trie {
+            count: root.word_count() as i32,
+            root,
+            reject_empty: self.reject_empty,
+            phonetic_index: None,
+        };
+        clone.set_phonetic_index(self.phonetic_index.is_some());
+        clone
+    }
+}
+
+impl Trie {
+    /// Counts every occurrence of each character across all stored words.
+    pub fn char_frequency_in_words(&self) -> HashMap<char, usize> {
+        // Each edge occurs once in every word stored below it.
+        fn count_recursive(node: &Node, frequencies: &mut HashMap<char, usize>) -> usize {
+            let mut words = usize::from(node.is_end);
+            for (&c, child) in &node.children {
+                let below = count_recursive(child, frequencies);
+                *frequencies.entry(c).or_default() += below;
+                words += below;
+            }
+            words
+        }
+        let mut frequencies = HashMap::new();
+        count_recursive(&self.root, &mut frequencies);
+        frequencies
+    }
+
+    /// Counts, for each character, the stored words having it at char index
+    /// `pos`.
+    pub fn char_frequency_at_position(&self, pos: usize) -> HashMap<char, usize> {
+        fn count_recursive(node: &Node, depth: usize, frequencies: &mut HashMap<char, usize>) {
+            for (&c, child) in &node.children {
+                if depth == 0 {
+                    *frequencies.entry(c).or_default() += child.word_count();
+                } else {
+                    count_recursive(child, depth - 1, frequencies);
+                }
+            }
+        }
+        let mut frequencies = HashMap::new();
+        count_recursive(&self.root, pos, &mut frequencies);
+        frequencies
+    }
+}
+
+impl Trie {
+    /// Builds a trie from `(parent_path, edge, is_end)` tuples, as produced by
+    /// `to_edge_list`. Multi-character edges create the intermediate nodes, and
+    /// an empty edge marks the parent itself.
+    pub fn from_prefix_tree(edges: &[(String, String, bool)]) -> Trie {
+        let mut trie = Trie::default();
+        for (parent_path, edge, is_end) in edges {
+            let mut current = &mut trie.root;
+            for c in parent_path.chars().chain(edge.chars()) {
+                current = current.children.entry(c).or_default();
+            }
+            current.is_end |= is_end;
+        }
+        trie.count = trie.root.word_count() as i32;
+        trie
+    }
+
+    /// Lists one `(parent_path, edge, is_end)` tuple per node in lexicographic
+    /// order. The root only appears, as `("", "", true)`, if `""` is stored.
+    pub fn to_edge_list(&self) -> Vec<(String, String, bool)> {
+        self.iter_all_nodes()
+            .filter_map(|(mut path, is_end)| match path.pop() {
+                Some(c) => Some((path, c.to_string(), is_end)),
+                None => is_end.then(|| (String::new(), String::new(), true)),
+            })
+            .collect()
+    }
+}
+
+impl Trie {
+    /// Finds the stored word sharing the longest common prefix with `query`,
+    /// returning it with the length of that prefix in `char`s. Among words
+    /// sharing the same prefix, the lexicographically smallest is picked, so a
+    /// stored word equal to the prefix itself wins.
+    pub fn longest_shared_prefix_with_stored_word(&self, query: &str) -> Option<(String, usize)> {
+        fn first_word(node: &Node, path: &mut String) -> bool {
+            if node.is_end {
+                return true;
+            }
+            let mut children: Vec<_> = node.children.iter().collect();
+            children.sort_unstable_by_key(|(&c, _)| c);
+            for (&c, child) in children {
+                path.push(c);
+                if first_word(child, path) {
+                    return true;
+                }
+                path.pop();
+            }
+            false
+        }
+        let mut nodes = vec![&self.root];
+        let mut path = String::new();
+        for c in query.chars() {
+            match nodes[nodes.len() - 1].children.get(&c) {
+                Some(next) => nodes.push(next),
+                None => break,
+            }
+            path.push(c);
+        }
+        // The deepest node reached may lead to no word, so back off to the
+        // deepest one that still does.
+        let shared = (1..nodes.len())
+            .rev()
+            .find(|&depth| nodes[depth].has_word())?;
+        let mut path: String = path.chars().take(shared).collect();
+        first_word(nodes[shared], &mut path).then_some((path, shared))
+    }
+}
+
+impl Trie {
+    /// Returns whether any stored word starts with `prefix`.
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        self.node_at_prefix(prefix).is_some_and(Node::has_word)
+    }
+
+    /// Returns whether nodes exist along `prefix`, whether or not they lead to
+    /// a stored word. `add` and `delete` never leave such nodes behind, so on a
+    /// trie built only with them a mismatch with `starts_with` means leaked
+    /// nodes.
+    pub fn path_exists(&self, prefix: &str) -> bool {
+        self.node_at_prefix(prefix).is_some()
+    }
+}
+
+impl Trie {
+    /// Returns the sorted byte offsets in `text` at which at least one stored
+    /// word begins. The empty string does not count as a match.
+    pub fn word_starts_at_every_position(&self, text: &str) -> Vec<usize> {
+        text.char_indices()
+            .map(|(start, _)| start)
+            .filter(|&start| {
+                let mut current = &self.root;
+                for c in text[start..].chars() {
+                    match current.children.get(&c) {
+                        Some(next) if next.is_end => return true,
+                        Some(next) => current = next,
+                        None => return false,
+                    }
+                }
+                false
+            })
+            .collect()
+    }
+}
+
+impl Trie {
+    /// Returns the sorted words whose char at each constrained position is in
+    /// the allowed set. A word too short to have a constrained position does
+    /// not match. Branches are pruned as soon as a constraint fails.
+    pub fn words_matching_char_class(&self, classes: &[(usize, HashSet<char>)]) -> Vec<String> {
+        fn collect_recursive(
+            node: &Node,
+            classes: &[(usize, HashSet<char>)],
+            min_len: usize,
+            path: &mut String,
+            depth: usize,
+            words: &mut Vec<String>,
+        ) {
+            if node.is_end && depth >= min_len {
+                words.push(path.clone());
+            }
+            for (&c, child) in &node.children {
+                let allowed = classes
+                    .iter()
+                    .all(|(pos, chars)| *pos != depth || chars.contains(&c));
+                if !allowed {
+                    continue;
+                }
+                path.push(c);
+                collect_recursive(child, classes, min_len, path, depth + 1, words);
+                path.pop();
+            }
+        }
+        let min_len = classes.iter().map(|(pos, _)| pos + 1).max().unwrap_or(0);
+        let mut words = Vec::new();
+        collect_recursive(
+            &self.root,
+            classes,
+            min_len,
+            &mut String::new(),
+            0,
+            &mut words,
+        );
+        words.sort();
+        words
+    }
+}
+
+impl Trie {
+    /// Counts the stored words containing `c` at least once.
+    pub fn count_words_containing_char(&self, c: char) -> usize {
+        fn count_recursive(node: &Node, c: char) -> usize {
+            node.children
+                .iter()
+                .map(|(&edge, child)| {
+                    if edge == c {
+                        // Every word below this edge contains `c`.
+                        child.word_count()
+                    } else {
+                        count_recursive(child, c)
+                    }
+                })
+                .sum()
+        }
+        count_recursive(&self.root, c)
+    }
+}
+
+impl Trie {
+    /// Iterates in lexicographic order over the words whose metadata is a `V`,
+    /// along with that metadata. Words are yielded as owned strings, since an
+    /// `Iterator` cannot lend out a path buffer it keeps modifying.
+    pub fn iter_words_with_value<V: Any>(&self) -> impl Iterator<Item = (String, &V)> + '_ {
+        self.nodes_iter().filter_map(|(path, node)| {
+            let value = node.metadata.as_ref()?.downcast_ref()?;
+            node.is_end.then_some((path, value))
+        })
+    }
+
+    /// Like `iter_words_with_value`, but allows updating every value in place.
+    pub fn iter_words_with_value_mut<V: Any>(
+        &mut self,
+    ) -> impl Iterator<Item = (String, &mut V)> + '_ {
+        fn collect_recursive<'a, V: Any>(
+            node: &'a mut Node,
+            path: &mut String,
+            values: &mut Vec<(String, &'a mut V)>,
+        ) {
+            if node.is_end {
+                if let Some(value) = node.metadata.as_mut().and_then(|meta| meta.downcast_mut()) {
+                    values.push((path.clone(), value));
+                }
+            }
+            for (&c, child) in node.children.iter_mut() {
+                path.push(c);
+                collect_recursive(child, path, values);
+                path.pop();
+            }
+        }
+        let mut values = Vec::new();
+        collect_recursive(&mut self.root, &mut String::new(), &mut values);
+        values.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        values.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_search() {
+        let mut trie = Trie::default();
+        trie.add("hello");
+        trie.add("hey");
+        trie.add("hi");
+
+        assert!(trie.search("hello"));
+        assert!(trie.search("hey"));
+        assert!(trie.search("hi"));
+        assert!(!trie.search("test"));
+        assert!(!trie.search("he"));
+    }
+
+    #[test]
+    fn test_get_child() {
+        let mut trie = Trie::default();
+        trie.add("hello");
+        trie.add("hey");
+        trie.add("hi");
+
+        let mut found = trie.get_child('h', &trie.root);
+        assert!(found.is_some());
+        found = trie.get_child('e', found.unwrap());
+        assert!(found.is_some());
+        found = trie.get_child('x', found.unwrap());
+        assert!(found.is_none());
+    }
+
+    #[test]
+    fn test_delete() {
+        let mut trie = Trie::default();
+        trie.add("hello");
+        trie.add("hey");
+        trie.add("hi");
+
+        assert!(trie.search("hello"));
+        trie.delete("hello").unwrap();
+        assert!(!trie.search("hello"));
+
+        assert!(trie.search("hey"));
+        trie.delete("hey").unwrap();
+        assert!(!trie.search("hey"));
+
+        assert!(trie.search("hi"));
+        trie.delete("hi").unwrap();
+        assert!(!trie.search("hi"));
+
+        assert_eq!(trie.count(), 0);
+    }
+
+    #[test]
+    fn test_delete_non_existing() {
+        let mut trie = Trie::default();
+        trie.add("hello");
+
+        let result = trie.delete("test");
+        assert_eq!(result, Err(TrieError::WordNotFound("test".to_string())));
+    }
+
+    #[test]
+    fn test_delete_prefix() {
+        let mut trie = Trie::default();
+        trie.add("hello");
+        trie.add("hey");
+
+        let result = trie.delete("he");
+        assert_eq!(result, Err(TrieError::WordNotFound("he".to_string())));
+    }
+
+    #[test]
+    fn test_contains_all() {
+        let mut trie = Trie::default();
+        trie.add("hello");
+        trie.add("hey");
+        trie.add("hi");
+
+        assert!(trie.contains_all(&["hello", "hey", "hi"]));
+        assert!(!trie.contains_all(&["test", "hey", "hi"]));
+        assert!(!trie.contains_all(&["hello", "hey", "test"]));
+        assert!(trie.contains_all(&[]));
+        assert!(!trie.contains_all(&["he", "hel"]));
+    }
+
+    #[test]
+    fn test_branching_factor() {
+        let mut trie = Trie::default();
+        assert_eq!(trie.max_branching_factor(), 0);
+        assert_eq!(trie.average_branching_factor(), 0.0);
+
+        trie.add("abcd");
+        assert_eq!(trie.max_branching_factor(), 1);
+        assert_eq!(trie.average_branching_factor(), 1.0);
+
+        trie.add("xa");
+        trie.add("xb");
+        trie.add("xc");
+        assert_eq!(trie.max_branching_factor(), 3);
+        // root: 2, a: 1, b: 1, c: 1, x: 3
+        assert_eq!(trie.average_branching_factor(), 8.0 / 5.0);
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut a = Trie::default();
+        a.add("hello");
+        let mut b = Trie::default();
+        b.add("hey");
+        b.add("hi");
+
+        a.swap(&mut b);
+        assert!(a.search("hey"));
+        assert!(a.search("hi"));
+        assert!(!a.search("hello"));
+        assert!(b.search("hello"));
+        assert!(!b.search("hey"));
+        assert_eq!(a.count(), 2);
+        assert_eq!(b.count(), 1);
+    }
+
+    #[test]
+    fn test_depth_at() {
+        let mut trie = Trie::default();
+        trie.add("hello");
+        trie.add("zażółć");
+
+        assert_eq!(trie.depth_at("hello"), Some(5));
+        assert_eq!(trie.depth_at("zażółć"), Some(6));
+        assert_ne!("zażółć".len(), 6);
+        assert_eq!(trie.depth_at("hell"), None);
+        assert_eq!(trie.depth_at("test"), None);
+        assert_eq!(trie.depth_at(""), None);
+
+        trie.add("");
+        assert_eq!(trie.depth_at(""), Some(0));
+    }
+
+    #[test]
+    fn test_unique_terminal_chars() {
+        let mut trie = Trie::default();
+        assert!(trie.unique_terminal_chars().is_empty());
+
+        trie.add("");
+        assert!(trie.unique_terminal_chars().is_empty());
+
+        trie.add("hello");
+        trie.add("hell");
+        trie.add("hey");
+        trie.add("way");
+        assert_eq!(trie.unique_terminal_chars(), HashSet::from(['o', 'l', 'y']));
+    }
+
+    #[test]
+    fn test_words_iter() {
+        let mut trie = Trie::default();
+        assert_eq!(trie.words_iter().next(), None);
+
+        trie.add("hi");
+        trie.add("hello");
+        trie.add("he");
+        trie.add("a");
+        assert_eq!(
+            trie.words_iter().collect::<Vec<_>>(),
+            vec!["a", "he", "hello", "hi"]
+        );
+    }
+
+    #[test]
+    fn test_prefix_compression_ratio() {
+        let mut trie = Trie::default();
+        assert_eq!(trie.prefix_compression_ratio(), 0.0);
+
+        trie.add("a");
+        trie.add("ab");
+        trie.add("abc");
+        trie.add("abcd");
+        assert_eq!(trie.total_node_count(), 4);
+        assert_eq!(trie.prefix_compression_ratio(), 0.4);
+
+        let mut disjoint = Trie::default();
+        disjoint.add("ab");
+        disjoint.add("cd");
+        assert_eq!(disjoint.prefix_compression_ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_iter_all_nodes() {
+        let mut trie = Trie::default();
+        trie.add("he");
+        trie.add("hello");
+        trie.add("a");
+
+        let nodes: Vec<_> = trie.iter_all_nodes().collect();
+        let expected = [
+            ("", false),
+            ("a", true),
+            ("h", false),
+            ("he", true),
+            ("hel", false),
+            ("hell", false),
+            ("hello", true),
+        ];
+        assert_eq!(nodes.len(), expected.len());
+        for ((path, is_end), (expected_path, expected_is_end)) in nodes.iter().zip(expected) {
+            assert_eq!(path, expected_path);
+            assert_eq!(*is_end, expected_is_end);
+        }
+    }
+
+    #[test]
+    fn test_add_duplicate() {
+        let mut trie = Trie::default();
+        trie.add("hello");
+        trie.add("hello");
+        assert_eq!(trie.count(), 1);
+
+        trie.delete("hello").unwrap();
+        assert_eq!(trie.count(), 0);
+    }
+
+    #[test]
+    fn test_subtrie_merge() {
+        let mut other = Trie::default();
+        other.add("foo");
+        other.add("bar");
+
+        let mut trie = Trie::default();
+        trie.add("my_foo");
+        trie.add("baz");
+        trie.subtrie_merge("my_", other);
+
+        assert!(trie.search("my_foo"));
+        assert!(trie.search("my_bar"));
+        assert!(trie.search("baz"));
+        assert!(!trie.search("foo"));
+        assert_eq!(trie.count(), 3);
+    }
+
+    #[test]
+    fn test_subtrie_merge_empty_prefix() {
+        let mut other = Trie::default();
+        other.add("foo");
+        other.add("bar");
+
+        let mut trie = Trie::default();
+        trie.add("foo");
+        trie.subtrie_merge("", other);
+
+        assert_eq!(trie.words_iter().collect::<Vec<_>>(), vec!["bar", "foo"]);
+        assert_eq!(trie.count(), 2);
+    }
+
+    #[test]
+    fn test_delete_unicode() {
+        let mut trie = Trie::default();
+        trie.add("zażółć");
+        trie.add("zażó");
+
+        trie.delete("zażółć").unwrap();
+        assert!(!trie.search("zażółć"));
+        assert!(trie.search("zażó"));
+        assert!(trie.delete("zażół").is_err());
+        assert_eq!(trie.count(), 1);
+    }
+
+    #[test]
+    fn test_reject_empty_string() {
+        let mut trie = Trie::default();
+        assert!(trie.accepts_empty_string());
+        trie.add("");
+        trie.add("hello");
+        assert!(trie.search(""));
+        assert_eq!(trie.count(), 2);
+
+        trie.set_accepts_empty_string(false);
+        assert!(!trie.accepts_empty_string());
+        assert!(!trie.search(""));
+        assert_eq!(trie.count(), 1);
+
+        trie.add("");
+        assert!(!trie.search(""));
+        assert_eq!(trie.count(), 1);
+        assert_eq!(trie.delete(""), Err(TrieError::EmptyWord));
+
+        trie.set_accepts_empty_string(true);
+        assert_eq!(trie.delete(""), Err(TrieError::WordNotFound(String::new())));
+    }
+
+    #[test]
+    fn test_from_trie() {
+        let mut trie = Trie::default();
+        trie.add("app");
+        trie.add("apple");
+        trie.add("apply");
+        trie.add("orange");
+
+        let sub = Trie::from_trie(trie, "app");
+        assert!(sub.search("le"));
+        assert!(sub.search("ly"));
+        assert!(sub.search(""));
+        assert!(!sub.search("orange"));
+        assert!(!sub.search("apple"));
+        assert_eq!(sub.count(), 3);
+
+        let mut trie = Trie::default();
+        trie.add("apple");
+        let empty = Trie::from_trie(trie, "bar");
+        assert_eq!(empty.count(), 0);
+        assert!(!empty.search(""));
+    }
+
+    #[test]
+    fn test_from_trie_subtrie_merge_round_trip() {
+        let mut trie = Trie::default();
+        trie.add("apple");
+        trie.add("apply");
+
+        let sub = Trie::from_trie(trie, "app");
+        let mut trie = Trie::default();
+        trie.subtrie_merge("app", sub);
+        assert_eq!(
+            trie.words_iter().collect::<Vec<_>>(),
+            vec!["apple", "apply"]
+        );
+        assert_eq!(trie.count(), 2);
+    }
+
+    #[test]
+    fn test_count_exact_prefix_matches() {
+        let mut trie = Trie::default();
+        trie.add("a");
+        trie.add("apple");
+        trie.add("apples");
+        trie.add("applesauce");
+        trie.add("banana");
+
+        assert_eq!(trie.count_exact_prefix_matches("applesauce"), 3);
+        assert_eq!(trie.count_exact_prefix_matches("applet"), 2);
+        assert_eq!(trie.count_exact_prefix_matches("a"), 0);
+        assert_eq!(trie.count_exact_prefix_matches("cherry"), 0);
+        assert_eq!(trie.count_exact_prefix_matches(""), 0);
+
+        trie.add("");
+        assert_eq!(trie.count_exact_prefix_matches("applesauce"), 4);
+    }
+
+    #[test]
+    fn test_longest_suffix_free_word() {
+        let mut trie = Trie::default();
+        assert_eq!(trie.longest_suffix_free_word(), None);
+
+        trie.add("ring");
+        trie.add("string");
+        trie.add("bring");
+        trie.add("cat");
+        assert_eq!(trie.longest_suffix_free_word(), Some("string".to_string()));
+
+        trie.add("bstring");
+        assert_eq!(trie.longest_suffix_free_word(), Some("bstring".to_string()));
+
+        let mut trie = Trie::default();
+        trie.add("dog");
+        trie.add("cat");
+        trie.add("at");
+        assert_eq!(trie.longest_suffix_free_word(), Some("cat".to_string()));
+    }
+
+    #[test]
+    fn test_error_message() {
+        let mut trie = Trie::default();
+        trie.add("hello");
+
+        let err = trie.delete("nonexistent").unwrap_err();
+        assert_eq!(err, TrieError::WordNotFound("nonexistent".to_string()));
+        assert_eq!(err.to_string(), "word 'nonexistent' not found in trie");
+    }
+
+    #[test]
+    fn test_new() {
+        let mut trie = Trie::new();
+        assert_eq!(trie.count(), Trie::default().count());
+        assert_eq!(trie.total_node_count(), 0);
+        assert!(!trie.search(""));
+        assert!(trie.accepts_empty_string());
+
+        trie.add("hello");
+        assert!(trie.search("hello"));
+        assert_eq!(trie.count(), 1);
+    }
+
+    #[test]
+    fn test_pop_word() {
+        let mut trie = Trie::default();
+        assert_eq!(trie.pop_word(), None);
+
+        let words = ["hello", "hey", "hi", "he"];
+        for word in words {
+            trie.add(word);
+        }
+
+        let mut popped = Vec::new();
+        while let Some(word) = trie.pop_word() {
+            popped.push(word);
+            assert_eq!(trie.count() as usize, words.len() - popped.len());
+        }
+        popped.sort();
+        let mut expected = words.to_vec();
+        expected.sort();
+        assert_eq!(popped, expected);
+        assert_eq!(trie.total_node_count(), 0);
+    }
+
+    #[test]
+    fn test_intersection_count() {
+        let mut a = Trie::default();
+        let mut b = Trie::default();
+        assert_eq!(a.intersection_count(&b), 0);
+        assert!(a.is_subset_of(&b));
+
+        for word in ["hello", "hey", "hi", "he", ""] {
+            a.add(word);
+        }
+        for word in ["hello", "he", "hell", "world", ""] {
+            b.add(word);
+        }
+        let naive = a.words_iter().filter(|w| b.search(w)).count();
+        assert_eq!(a.intersection_count(&b), naive);
+        assert_eq!(a.intersection_count(&b), 3);
+        assert_eq!(b.intersection_count(&a), 3);
+        assert!(!a.is_subset_of(&b));
+
+        let mut c = Trie::default();
+        c.add("hello");
+        c.add("he");
+        assert!(c.is_subset_of(&a));
+        assert!(c.is_subset_of(&b));
+        assert!(!a.is_subset_of(&c));
+    }
+
+    #[test]
+    fn test_words_not_in_and_also_in() {
+        let mut a = Trie::default();
+        for word in ["hi", "hello", "hey", "he"] {
+            a.add(word);
+        }
+        let mut b = Trie::default();
+        for word in ["hello", "he", "world"] {
+            b.add(word);
+        }
+
+        assert_eq!(a.words_not_in(&b), vec!["hey", "hi"]);
+        assert_eq!(b.words_not_in(&a), vec!["world"]);
+        assert_eq!(a.words_also_in(&b), vec!["he", "hello"]);
+        assert_eq!(a.words_also_in(&b), b.words_also_in(&a));
+
+        let empty = Trie::default();
+        assert_eq!(a.words_not_in(&empty), a.words_iter().collect::<Vec<_>>());
+        assert!(a.words_also_in(&empty).is_empty());
+        assert!(empty.words_not_in(&a).is_empty());
+        assert!(a.words_not_in(&a).is_empty());
+    }
+
+    #[test]
+    fn test_root_node() {
+        let mut trie = Trie::default();
+        trie.add("he");
+        trie.add("hello");
+        trie.add("hi");
+
+        let root = trie.root_node();
+        assert!(!root.is_end());
+        assert_eq!(root.children().count(), 1);
+
+        let h = root.get_child('h').unwrap();
+        let mut edges: Vec<char> = h.children().map(|(c, _)| c).collect();
+        edges.sort();
+        assert_eq!(edges, vec!['e', 'i']);
+
+        let he = h.get_child('e').unwrap();
+        assert!(he.is_end());
+        assert!(he.get_child('x').is_none());
+        let hello = "llo"
+            .chars()
+            .try_fold(he, |node, c| node.get_child(c))
+            .unwrap();
+        assert!(hello.is_end());
+        assert_eq!(hello.children().count(), 0);
+    }
+
+    #[test]
+    fn test_display() {
+        let mut trie = Trie::default();
+        assert_eq!(trie.to_string(), "");
+
+        trie.add("hi");
+        trie.add("hello");
+        trie.add("hey");
+        assert_eq!(trie.to_string(), "hello\nhey\nhi");
+        assert_eq!(format!("{trie}"), "hello\nhey\nhi");
+    }
+
+    #[test]
+    fn test_display_from_str_round_trip() {
+        let mut trie = Trie::default();
+        for word in ["hi", "hello", "hey", "", "zażółć"] {
+            trie.add(word);
+        }
+
+        let parsed: Trie = trie.to_string().parse().unwrap();
+        assert_eq!(
+            parsed.words_iter().collect::<Vec<_>>(),
+            trie.words_iter().collect::<Vec<_>>()
+        );
+        assert_eq!(parsed.count(), trie.count());
+    }
+
+    #[test]
+    fn test_display_from_str_only_empty_word() {
+        let mut trie = Trie::default();
+        trie.add("");
+        assert_eq!(trie.to_string(), "\n");
+
+        let parsed: Trie = trie.to_string().parse().unwrap();
+        assert!(parsed == trie);
+        assert_eq!(parsed.count(), 1);
+
+        let empty: Trie = Trie::default().to_string().parse().unwrap();
+        assert_eq!(empty.count(), 0);
+    }
+
+    #[test]
+    fn test_display_from_str_escapes() {
+        let mut trie = Trie::default();
+        for word in ["a\nb", "back\\slash", "cr\r", "plain"] {
+            trie.add(word);
+        }
+        assert_eq!(trie.to_string(), "a\\nb\nback\\\\slash\ncr\\r\nplain");
+
+        let parsed: Trie = trie.to_string().parse().unwrap();
+        assert!(parsed == trie);
+        assert!(parsed.search("a\nb"));
+        assert!(!parsed.search("a"));
+        assert_eq!(parsed.count(), 4);
+    }
+
+    #[test]
+    fn test_levenshtein_distance() {
+        assert_eq!(levenshtein_distance("", ""), 0);
+        assert_eq!(levenshtein_distance("", "hello"), 5);
+        assert_eq!(levenshtein_distance("hello", ""), 5);
+        assert_eq!(levenshtein_distance("hello", "hello"), 0);
+        assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+        assert_eq!(levenshtein_distance("flaw", "lawn"), 2);
+        assert_eq!(levenshtein_distance("their", "there"), 2);
+        assert_eq!(levenshtein_distance("zażółć", "zazolc"), 4);
+    }
+
+    #[test]
+    fn test_soundex() {
+        assert_eq!(soundex("Robert"), "R163");
+        assert_eq!(soundex("Rupert"), "R163");
+        assert_eq!(soundex("Ashcraft"), "A261");
+        assert_eq!(soundex("Tymczak"), "T522");
+        assert_eq!(soundex("Pfister"), "P236");
+        assert_eq!(soundex("their"), "T600");
+        assert_eq!(soundex(""), "");
+        assert_eq!(soundex("123"), "");
+    }
+
+    #[test]
+    fn test_phonetic_search() {
+        for indexed in [false, true] {
+            let mut trie = Trie::default();
+            trie.set_phonetic_index(indexed);
+            for word in ["there", "their", "robert", "rupert", "hello", "123"] {
+                trie.add(word);
+            }
+
+            assert_eq!(trie.phonetic_search("thier"), vec!["their", "there"]);
+            assert_eq!(trie.phonetic_search("Rubert"), vec!["robert", "rupert"]);
+            assert_eq!(trie.phonetic_search("world"), Vec::<String>::new());
+            assert_eq!(trie.phonetic_search("123"), Vec::<String>::new());
+
+            trie.delete("there").unwrap();
+            assert_eq!(trie.phonetic_search("thier"), vec!["their"]);
+        }
+    }
+
+    #[test]
+    fn test_phonetic_index_built_from_existing_words() {
+        let mut trie = Trie::default();
+        trie.add("there");
+        trie.add("their");
+        trie.set_phonetic_index(true);
+        assert_eq!(trie.phonetic_search("thier"), vec!["their", "there"]);
+
+        let sub = Trie::from_trie(trie, "the");
+        assert_eq!(sub.phonetic_search("ri"), vec!["re"]);
+    }
+
+    #[test]
+    fn test_frequency_trie_add() {
+        let mut trie = FrequencyTrie::default();
+        trie.add("hello");
+        trie.add("hello");
+        trie.add("he");
+
+        assert_eq!(trie.frequency("hello"), 2);
+        assert_eq!(trie.frequency("he"), 1);
+        assert_eq!(trie.frequency("hel"), 0);
+        assert_eq!(trie.frequency("test"), 0);
+        assert_eq!(trie.count(), 2);
+    }
+
+    #[test]
+    fn test_word_frequency_map() {
+        let mut trie = FrequencyTrie::default();
+        for word in ["the", "fox", "the", "then", "the"] {
+            trie.add(word);
+        }
+
+        let map = trie.word_frequency_map();
+        let expected = HashMap::from([
+            ("the".to_string(), 3),
+            ("fox".to_string(), 1),
+            ("then".to_string(), 1),
+        ]);
+        assert_eq!(map, expected);
+
+        let rebuilt = FrequencyTrie::from_frequency_map(map);
+        assert_eq!(rebuilt.word_frequency_map(), expected);
+        assert_eq!(rebuilt.frequency("the"), 3);
+        assert_eq!(rebuilt.count(), 3);
+
+        let skipped = FrequencyTrie::from_frequency_map(HashMap::from([("zero".to_string(), 0)]));
+        assert_eq!(skipped.count(), 0);
+        assert!(skipped.word_frequency_map().is_empty());
+    }
+
+    #[test]
+    fn test_merge_frequency() {
+        let mut a = FrequencyTrie::default();
+        for word in ["hello", "hello", "hello", "mine"] {
+            a.add(word);
+        }
+        let mut b = FrequencyTrie::default();
+        for word in ["hello", "hello", "hello", "hello", "hello", "yours"] {
+            b.add(word);
+        }
+
+        let merged = a.merged_with(&b);
+        assert_eq!(a.frequency("hello"), 3);
+        assert_eq!(b.frequency("hello"), 5);
+
+        a.merge_frequency(b);
+        for trie in [&a, &merged] {
+            assert_eq!(trie.frequency("hello"), 8);
+            assert_eq!(trie.frequency("mine"), 1);
+            assert_eq!(trie.frequency("yours"), 1);
+            assert_eq!(trie.count(), 3);
+        }
+    }
+
+    #[test]
+    fn test_iter_by_frequency() {
+        let mut trie = FrequencyTrie::default();
+        for word in ["b", "a", "c", "c", "c", "b", "d", "d"] {
+            trie.add(word);
+        }
+
+        let descending: Vec<_> = trie.iter_by_frequency(SortOrder::Descending).collect();
+        assert_eq!(
+            descending,
+            vec![
+                ("c".to_string(), 3),
+                ("b".to_string(), 2),
+                ("d".to_string(), 2),
+                ("a".to_string(), 1),
+            ]
+        );
+
+        let ascending: Vec<_> = trie.iter_by_frequency(SortOrder::Ascending).collect();
+        assert_eq!(
+            ascending,
+            vec![
+                ("a".to_string(), 1),
+                ("b".to_string(), 2),
+                ("d".to_string(), 2),
+                ("c".to_string(), 3),
+            ]
+        );
+
+        assert_eq!(
+            FrequencyTrie::default()
+                .iter_by_frequency(SortOrder::Ascending)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_ord() {
+        let a: Trie = "apple\nbanana".parse().unwrap();
+        let b: Trie = "apple\ncherry".parse().unwrap();
+        let a2: Trie = "banana\napple".parse().unwrap();
+        let shorter: Trie = "apple".parse().unwrap();
+
+        assert!(a < b);
+        assert!(b > a);
+        assert!(a == a2);
+        assert!(a != b);
+        assert_eq!(a.cmp(&a2), Ordering::Equal);
+        assert!(shorter < a);
+        assert!(Trie::default() < shorter);
+
+        let mut sorted = [b, a, shorter];
+        sorted.sort();
+        let words: Vec<String> = sorted.iter().map(|t| t.to_string()).collect();
+        assert_eq!(words, vec!["apple", "apple\nbanana", "apple\ncherry"]);
+    }
+
+    #[test]
+    fn test_trie_from_str_pairs() {
+        let trie =
+            trie_from_str_pairs(&[("user:", "alice"), ("user:", "bob"), ("group:", "admin")]);
+        assert_eq!(
+            trie.words_iter().collect::<Vec<_>>(),
+            vec!["group:admin", "user:alice", "user:bob"]
+        );
+        assert_eq!(trie_from_str_pairs(&[]).count(), 0);
+    }
+
+    #[test]
+    fn test_trie_from_segments() {
+        let trie = trie_from_segments([vec!["a", "b", "c"], vec!["x"], vec![]]);
+        assert_eq!(trie.words_iter().collect::<Vec<_>>(), vec!["", "abc", "x"]);
+        assert_eq!(trie.count(), 3);
+    }
+
+    #[test]
+    fn test_search_prefix_with_count() {
+        let mut trie = Trie::default();
+        trie.add("he");
+        trie.add("hello");
+        trie.add("hey");
+        trie.add("hi");
+
+        assert_eq!(trie.search_prefix_with_count("he"), (true, 3));
+        assert_eq!(trie.search_prefix_with_count("h"), (true, 4));
+        assert_eq!(trie.search_prefix_with_count("hell"), (true, 1));
+        assert_eq!(trie.search_prefix_with_count("hello"), (true, 1));
+        assert_eq!(trie.search_prefix_with_count("help"), (false, 0));
+        assert_eq!(trie.search_prefix_with_count(""), (true, 4));
+    }
+
+    #[test]
+    fn test_delete_strict() {
+        let mut trie = Trie::default();
+        trie.add("he");
+        trie.add("hello");
+
+        let err = trie.delete_strict("he").unwrap_err();
+        assert_eq!(err, TrieError::PrefixIsWord("he".to_string()));
+        assert_eq!(
+            err.to_string(),
+            "word 'he' is a prefix of other stored words"
+        );
+        assert!(trie.search("he"));
+        assert_eq!(trie.count(), 2);
+
+        assert_eq!(
+            trie.delete_strict("hell"),
+            Err(TrieError::WordNotFound("hell".to_string()))
+        );
+
+        trie.delete_strict("hello").unwrap();
+        trie.delete_strict("he").unwrap();
+        assert_eq!(trie.count(), 0);
+
+        let mut preview = "ab\nabcdef".parse::<Trie>().unwrap().shallow_clone(3);
+        assert!(!preview.starts_with("abc"));
+        preview.delete_strict("ab").unwrap();
+        assert_eq!(preview.count(), 0);
+    }
+
+    #[test]
+    fn test_capacity_hint() {
+        let mut trie = Trie::default();
+        assert_eq!(trie.capacity_hint(), 0);
+
+        for word in ["hello", "hey", "hi", "abc", "abd", "xyz"] {
+            trie.add(word);
+        }
+        assert!(trie.capacity_hint() >= trie.total_node_count());
+
+        for word in ["hello", "hey", "hi", "abc", "abd", "xyz"] {
+            trie.delete(word).unwrap();
+        }
+        assert_eq!(trie.total_node_count(), 0);
+        // The root keeps its allocation after its children are removed.
+        assert!(trie.capacity_hint() > 0);
+    }
+
+    #[test]
+    fn test_approximate_word_count_with_prefix() {
+        let mut trie = Trie::default();
+        for word in [
+            "pre", "prefix", "prefab", "press", "pressure", "pressed", "other",
+        ] {
+            trie.add(word);
+        }
+
+        // Every match is at most three characters below "pres".
+        assert_eq!(trie.approximate_word_count_with_prefix("pres"), 3);
+        assert_eq!(trie.approximate_word_count_with_prefix("missing"), 0);
+        // Below "pref" and "pres" each level has the same shape on all branches.
+        assert_eq!(trie.approximate_word_count_with_prefix("p"), 6);
+        // From the root, the probe below "pre" only walks "pref", which holds
+        // fewer words than "pres", and so underestimates.
+        assert_eq!(trie.approximate_word_count_with_prefix(""), 6);
+
+        // Here the probe walks "aaab", which is larger than "aaac".
+        let skewed: Trie = "aaaba\naaabb\naaabc\naaac".parse().unwrap();
+        assert_eq!(skewed.approximate_word_count_with_prefix(""), 6);
+        assert_eq!(skewed.search_prefix_with_count(""), (true, 4));
+    }
+
+    #[test]
+    fn test_approximate_word_count_with_prefix_extrapolates() {
+        // 25,000 pseudo-random words of 'x' followed by five letters from 'a'
+        // to 'j', drawn with a linear congruential generator.
+        let mut state: u64 = 42;
+        let mut trie = Trie::default();
+        while trie.count() < 25_000 {
+            let mut word = String::from("x");
+            for _ in 0..5 {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                word.push(char::from(b'a' + (state >> 33) as u8 % 10));
+            }
+            trie.add(&word);
+        }
+
+        for prefix in ["", "x", "xa", "xaj"] {
+            let (_, exact) = trie.search_prefix_with_count(prefix);
+            let estimate = trie.approximate_word_count_with_prefix(prefix);
+            assert!(
+                estimate.abs_diff(exact) * 10 <= exact,
+                "{prefix:?}: estimated {estimate}, stored {exact}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_metadata() {
+        let mut trie = Trie::default();
+        assert_eq!(trie.insert_with_metadata("hello", 1usize), None);
+        assert_eq!(trie.insert_with_metadata("hello", 2usize), Some(1));
+        assert_eq!(trie.insert_with_metadata("world", "main.rs"), None);
+        trie.add("plain");
+
+        assert_eq!(trie.get_metadata::<usize>("hello"), Some(&2));
+        assert_eq!(trie.get_metadata::<&str>("world"), Some(&"main.rs"));
+        assert_eq!(trie.get_metadata::<usize>("world"), None);
+        assert_eq!(trie.get_metadata::<usize>("plain"), None);
+        assert_eq!(trie.get_metadata::<usize>("hell"), None);
+        assert_eq!(trie.count(), 3);
+
+        trie.update_metadata("hello", |line: Option<usize>| line.unwrap_or(0) + 10);
+        assert_eq!(trie.get_metadata::<usize>("hello"), Some(&12));
+        trie.update_metadata("new", |line: Option<usize>| line.unwrap_or(0) + 10);
+        assert_eq!(trie.get_metadata::<usize>("new"), Some(&10));
+        assert_eq!(trie.count(), 4);
+    }
+
+    #[test]
+    fn test_metadata_cleared_on_delete() {
+        let mut trie = Trie::default();
+        trie.insert_with_metadata("hello", 1usize);
+        trie.insert_with_metadata("hell", 2usize);
+
+        trie.delete("hell").unwrap();
+        assert_eq!(trie.get_metadata::<usize>("hell"), None);
+        assert_eq!(trie.get_metadata::<usize>("hello"), Some(&1));
+
+        trie.add("hell");
+        assert_eq!(trie.get_metadata::<usize>("hell"), None);
+    }
+
+    #[test]
+    fn test_error_clone_and_eq() {
+        let err = TrieError::WordNotFound("test".to_string());
+        let errors = [err.clone(), TrieError::EmptyWord];
+        assert_eq!(errors[0], err);
+        assert_ne!(errors[0], TrieError::WordNotFound("other".to_string()));
+        assert_ne!(errors[1], err);
+    }
+
+    #[test]
+    fn test_search_with_prefix_info() {
+        let mut trie = Trie::default();
+        assert_eq!(trie.search_with_prefix_info(""), SearchOutcome::NotInTrie);
+
+        trie.add("he");
+        trie.add("hello");
+        trie.add("help");
+
+        assert_eq!(
+            trie.search_with_prefix_info("test"),
+            SearchOutcome::NotInTrie
+        );
+        assert_eq!(
+            trie.search_with_prefix_info("hello"),
+            SearchOutcome::ExactMatch
+        );
+        assert_eq!(
+            trie.search_with_prefix_info("hel"),
+            SearchOutcome::PrefixOnly {
+                completions_estimate: 2
+            }
+        );
+        assert_eq!(
+            trie.search_with_prefix_info("he"),
+            SearchOutcome::ExactMatchAndPrefix {
+                completions_estimate: 2
+            }
+        );
+        assert_eq!(
+            trie.search_with_prefix_info(""),
+            SearchOutcome::PrefixOnly {
+                completions_estimate: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_search_with_prefix_info_on_shallow_clone() {
+        let trie: Trie = "ab\nabcdef\nxyz".parse().unwrap();
+        let preview = trie.shallow_clone(2);
+
+        assert_eq!(
+            preview.search_with_prefix_info("ab"),
+            SearchOutcome::ExactMatch
+        );
+        assert_eq!(
+            preview.search_with_prefix_info("xy"),
+            SearchOutcome::NotInTrie
+        );
+        assert_eq!(
+            preview.search_with_prefix_info("x"),
+            SearchOutcome::NotInTrie
+        );
+        assert_eq!(
+            preview.search_with_prefix_info("a"),
+            SearchOutcome::PrefixOnly {
+                completions_estimate: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_words_with_exact_char_set() {
+        let mut trie = Trie::default();
+        for word in [
+            "listen", "silent", "enlist", "tinsel", "list", "inlets", "stale", "tassel", "lets",
+        ] {
+            trie.add(word);
+        }
+
+        let chars: HashSet<char> = "listen".chars().collect();
+        assert_eq!(
+            trie.words_with_exact_char_set(&chars),
+            vec!["enlist", "inlets", "listen", "silent", "tinsel"]
+        );
+
+        let chars: HashSet<char> = "tales".chars().collect();
+        assert_eq!(
+            trie.words_with_exact_char_set(&chars),
+            vec!["stale", "tassel"]
+        );
+
+        let chars: HashSet<char> = "lets".chars().collect();
+        assert_eq!(trie.words_with_exact_char_set(&chars), vec!["lets"]);
+        assert!(trie
+            .words_with_exact_char_set(&HashSet::from(['x']))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_anagram_search() {
+        let mut trie = Trie::default();
+        for word in [
+            "listen", "silent", "enlist", "tinsel", "inlets", "list", "listens", "stale", "least",
+            "slate", "tassel",
+        ] {
+            trie.add(word);
+        }
+
+        assert_eq!(
+            trie.anagram_search("listen"),
+            vec!["enlist", "inlets", "listen", "silent", "tinsel"]
+        );
+        assert_eq!(
+            trie.anagram_search("tales"),
+            vec!["least", "slate", "stale"]
+        );
+        assert_eq!(trie.anagram_search("tassle"), vec!["tassel"]);
+        assert!(trie.anagram_search("tale").is_empty());
+        assert!(trie.anagram_search("").is_empty());
+
+        trie.add("");
+        assert_eq!(trie.anagram_search(""), vec![""]);
+    }
+
+    #[test]
+    fn test_remove_words_not_in() {
+        let words = ["hello", "hey", "hi", "he"];
+        let mut superset: Trie = "hello\nhey\nhi\nhe\nworld".parse().unwrap();
+        let mut trie: Trie = words.join("\n").parse().unwrap();
+        trie.remove_words_not_in(&superset);
+        assert_eq!(trie.count(), 4);
+        assert_eq!(
+            trie.words_iter().collect::<Vec<_>>(),
+            vec!["he", "hello", "hey", "hi"]
+        );
+
+        let disjoint: Trie = "apple\nbanana".parse().unwrap();
+        trie.remove_words_not_in(&disjoint);
+        assert_eq!(trie.count(), 0);
+        assert_eq!(trie.total_node_count(), 0);
+
+        let partial: Trie = "hello\nhi\nbanana".parse().unwrap();
+        superset.remove_words_not_in(&partial);
+        assert_eq!(superset.count(), 2);
+        assert_eq!(
+            superset.words_iter().collect::<Vec<_>>(),
+            vec!["hello", "hi"]
+        );
+    }
+
+    #[test]
+    fn test_prefix_overlap_ratio() {
+        let a: Trie = "hello\nhelp".parse().unwrap();
+        let b: Trie = "hex\nhelm".parse().unwrap();
+        // a has h, he, hel, hell, hello, help; b shares h, he, hel.
+        assert_eq!(a.prefix_overlap_ratio(&b), 0.5);
+        // b has h, he, hex, hel, helm; a shares h, he, hel.
+        assert_eq!(b.prefix_overlap_ratio(&a), 0.6);
+
+        assert_eq!(a.prefix_overlap_ratio(&a), 1.0);
+        let disjoint: Trie = "world".parse().unwrap();
+        assert_eq!(a.prefix_overlap_ratio(&disjoint), 0.0);
+        assert_eq!(Trie::default().prefix_overlap_ratio(&a), 0.0);
+    }
+
+    #[test]
+    fn test_batch_delete() {
+        let mut trie: Trie = "hello\nhey\nhi".parse().unwrap();
+        assert_eq!(trie.batch_delete(["hello", "hey"]), (2, vec![]));
+        assert_eq!(trie.count(), 1);
+
+        assert_eq!(
+            trie.batch_delete(["test", "world"]),
+            (0, vec!["test".to_string(), "world".to_string()])
+        );
+
+        let mut trie: Trie = "hello\nhey\nhi".parse().unwrap();
+        let words = vec![
+            "hi".to_string(),
+            "he".to_string(),
+            "hello".to_string(),
+            "hi".to_string(),
+        ];
+        assert_eq!(
+            trie.batch_delete(&words),
+            (2, vec!["he".to_string(), "hi".to_string()])
+        );
+        assert_eq!(trie.words_iter().collect::<Vec<_>>(), vec!["hey"]);
+    }
+
+    #[test]
+    fn test_shallow_clone() {
+        let trie: Trie = "a\nab\nabc\nabcd\nxyz".parse().unwrap();
+
+        let clone = trie.shallow_clone(2);
+        assert!(clone.search("a"));
+        assert!(clone.search("ab"));
+        assert!(!clone.search("abc"));
+        assert!(!clone.search("xyz"));
+        assert_eq!(clone.count(), 2);
+        assert!(clone
+            .root_node()
+            .get_child('x')
+            .and_then(|x| x.get_child('y'))
+            .is_some());
+        assert_eq!(clone.total_node_count(), 4);
+
+        assert_eq!(trie.shallow_clone(0).count(), 0);
+        assert!(trie.shallow_clone(10) == trie);
+        assert_eq!(trie.count(), 5);
+    }
+
+    #[test]
+    fn test_shallow_clone_approximate_count() {
+        let trie: Trie = "abcdefg\nab\nabxyzw\nqrstu".parse().unwrap();
+        let clone = trie.shallow_clone(4);
+        // Probes skip the cut-off paths, which lead to no word.
+        for prefix in ["", "a", "ab", "abc", "abx", "q"] {
+            let (_, exact) = clone.search_prefix_with_count(prefix);
+            assert_eq!(clone.approximate_word_count_with_prefix(prefix), exact);
+        }
+        assert_eq!(clone.approximate_word_count_with_prefix(""), 1);
+        assert_eq!(clone.approximate_word_count_with_prefix("q"), 0);
+    }
+
+    #[test]
+    fn test_char_frequency_in_words() {
+        let trie: Trie = "hello\nworld\nhelp".parse().unwrap();
+        let frequencies = trie.char_frequency_in_words();
+        assert_eq!(frequencies[&'l'], 4);
+        assert_eq!(frequencies[&'o'], 2);
+        assert_eq!(frequencies[&'h'], 2);
+        assert_eq!(frequencies[&'p'], 1);
+        assert_eq!(frequencies.values().sum::<usize>(), 14);
+        assert!(Trie::default().char_frequency_in_words().is_empty());
+    }
+
+    #[test]
+    fn test_char_frequency_at_position() {
+        let trie: Trie = "hello\nworld\nhelp\nhi".parse().unwrap();
+        assert_eq!(
+            trie.char_frequency_at_position(0),
+            HashMap::from([('h', 3), ('w', 1)])
+        );
+        assert_eq!(
+            trie.char_frequency_at_position(1),
+            HashMap::from([('e', 2), ('i', 1), ('o', 1)])
+        );
+        assert_eq!(
+            trie.char_frequency_at_position(4),
+            HashMap::from([('o', 1), ('d', 1)])
+        );
+        assert!(trie.char_frequency_at_position(5).is_empty());
+    }
+
+    fn edge(parent: &str, edge: &str, is_end: bool) -> (String, String, bool) {
+        (parent.to_string(), edge.to_string(), is_end)
+    }
+
+    #[test]
+    fn test_to_edge_list() {
+        let trie: Trie = "he\nhi".parse().unwrap();
+        assert_eq!(
+            trie.to_edge_list(),
+            vec![
+                edge("", "h", false),
+                edge("h", "e", true),
+                edge("h", "i", true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_prefix_tree() {
+        let trie = Trie::from_prefix_tree(&[
+            edge("", "", true),
+            edge("", "he", true),
+            edge("he", "llo", true),
+            edge("he", "y", false),
+        ]);
+        assert!(trie.search(""));
+        assert!(trie.search("he"));
+        assert!(trie.search("hello"));
+        assert!(!trie.search("hey"));
+        assert!(!trie.search("hell"));
+        assert_eq!(trie.count(), 3);
+
+        let trie: Trie = "\nhello\nhelp\nzażółć".parse().unwrap();
+        let rebuilt = Trie::from_prefix_tree(&trie.to_edge_list());
+        assert!(rebuilt == trie);
+        assert_eq!(rebuilt.total_node_count(), trie.total_node_count());
+    }
+
+    #[test]
+    fn test_longest_shared_prefix_with_stored_word() {
+        let mut trie: Trie = "help\nhello\nworld".parse().unwrap();
+        assert_eq!(
+            trie.longest_shared_prefix_with_stored_word("helpful"),
+            Some(("help".to_string(), 4))
+        );
+        assert_eq!(
+            trie.longest_shared_prefix_with_stored_word("helium"),
+            Some(("hello".to_string(), 3))
+        );
+        assert_eq!(
+            trie.longest_shared_prefix_with_stored_word("hello"),
+            Some(("hello".to_string(), 5))
+        );
+        assert_eq!(
+            trie.longest_shared_prefix_with_stored_word("wow"),
+            Some(("world".to_string(), 2))
+        );
+        assert_eq!(trie.longest_shared_prefix_with_stored_word("xyz"), None);
+        assert_eq!(trie.longest_shared_prefix_with_stored_word(""), None);
+
+        trie.add("");
+        assert_eq!(trie.longest_shared_prefix_with_stored_word("xyz"), None);
+    }
+
+    #[test]
+    fn test_longest_shared_prefix_with_stored_word_on_dangling_paths() {
+        let preview = "abcdefg\nab".parse::<Trie>().unwrap().shallow_clone(4);
+        assert_eq!(
+            preview.longest_shared_prefix_with_stored_word("abcz"),
+            Some(("ab".to_string(), 2))
+        );
+        assert_eq!(
+            preview.longest_shared_prefix_with_stored_word("abcd"),
+            Some(("ab".to_string(), 2))
+        );
+
+        let trie = Trie::from_prefix_tree(&[edge("", "he", true), edge("he", "lp", false)]);
+        assert_eq!(
+            trie.longest_shared_prefix_with_stored_word("help"),
+            Some(("he".to_string(), 2))
+        );
+        let trie = Trie::from_prefix_tree(&[edge("", "xy", false)]);
+        assert_eq!(trie.longest_shared_prefix_with_stored_word("xy"), None);
+    }
+
+    #[test]
+    fn test_starts_with_and_path_exists() {
+        let mut trie: Trie = "hello\nhey".parse().unwrap();
+        for prefix in ["", "h", "he", "hel", "hello", "hey"] {
+            assert!(trie.starts_with(prefix));
+            assert!(trie.path_exists(prefix));
+        }
+        for prefix in ["hex", "helloo", "x"] {
+            assert!(!trie.starts_with(prefix));
+            assert!(!trie.path_exists(prefix));
+        }
+
+        trie.delete("hello").unwrap();
+        assert!(!trie.starts_with("hel"));
+        assert!(!trie.path_exists("hel"));
+
+        let preview = trie.shallow_clone(2);
+        assert!(preview.path_exists("he"));
+        assert!(!preview.starts_with("he"));
+        assert!(!Trie::default().starts_with(""));
+        assert!(Trie::default().path_exists(""));
+
+        let mut preview = "ab\nabcdef".parse::<Trie>().unwrap().shallow_clone(4);
+        preview.delete("ab").unwrap();
+        assert!(!preview.path_exists("a"));
+        assert_eq!(preview.total_node_count(), 0);
+    }
+
+    #[test]
+    fn test_word_starts_at_every_position() {
+        let mut trie: Trie = "he\nhello".parse().unwrap();
+        assert_eq!(
+            trie.word_starts_at_every_position("say hello here"),
+            vec![4, 10]
+        );
+        assert_eq!(trie.word_starts_at_every_position("hehe"), vec![0, 2]);
+        assert_eq!(trie.word_starts_at_every_position("h"), Vec::<usize>::new());
+        assert_eq!(trie.word_starts_at_every_position(""), Vec::<usize>::new());
+
+        trie.add("");
+        trie.add("łą");
+        assert_eq!(trie.word_starts_at_every_position("ółąhe"), vec![2, 6]);
+    }
+
+    #[test]
+    fn test_words_matching_char_class() {
+        let trie: Trie = "crane\ncrate\nslate\ntrace\nbrace\ngrace\nplace\nca"
+            .parse()
+            .unwrap();
+        let not_e: HashSet<char> = ('a'..='z').filter(|&c| c != 'e').collect();
+
+        assert_eq!(
+            trie.words_matching_char_class(&[(2, HashSet::from(['a'])), (3, HashSet::from(['c']))]),
+            vec!["brace", "grace", "place", "trace"]
+        );
+        assert_eq!(
+            trie.words_matching_char_class(&[(1, HashSet::from(['r'])), (4, not_e.clone())]),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            trie.words_matching_char_class(&[(0, HashSet::from(['c', 's'])), (3, not_e)]),
+            vec!["crane", "crate", "slate"]
+        );
+        assert_eq!(
+            trie.words_matching_char_class(&[(1, HashSet::from(['a', 'l']))]),
+            vec!["ca", "place", "slate"]
+        );
+        assert_eq!(trie.words_matching_char_class(&[]).len(), 8);
+    }
+
+    #[test]
+    fn test_count_words_containing_char() {
+        let trie: Trie = "apple\nbanana\ngrape\npapaya\nkiwi".parse().unwrap();
+        assert_eq!(trie.count_words_containing_char('z'), 0);
+        assert_eq!(trie.count_words_containing_char('a'), 4);
+        assert_eq!(trie.count_words_containing_char('p'), 3);
+        assert_eq!(trie.count_words_containing_char('i'), 1);
+
+        let trie: Trie = "aa\nab\nba".parse().unwrap();
+        assert_eq!(trie.count_words_containing_char('a'), 3);
+        assert_eq!(Trie::default().count_words_containing_char('a'), 0);
+    }
+
+    #[test]
+    fn test_error_conversions() {
+        let mut trie = Trie::default();
+        let err = trie.delete("missing").map_err(String::from).unwrap_err();
+        assert_eq!(
+            err,
+            TrieError::WordNotFound("missing".to_string()).to_string()
+        );
+
+        fn delete_io(trie: &mut Trie, word: &str) -> io::Result<()> {
+            trie.delete(word)?;
+            Ok(())
+        }
+        let err = delete_io(&mut trie, "missing").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(err.to_string(), "word 'missing' not found in trie");
+
+        trie.set_accepts_empty_string(false);
+        let err = delete_io(&mut trie, "").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_iter_words_with_value() {
+        let mut trie = Trie::default();
+        let words = [
+            "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ];
+        for (i, word) in words.iter().enumerate() {
+            trie.insert_with_metadata(word, i);
+        }
+        trie.insert_with_metadata("other", "not a usize");
+        trie.add("plain");
+
+        let mut expected: Vec<(String, usize)> = words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| (w.to_string(), i))
+            .collect();
+        expected.sort();
+        let values: Vec<(String, usize)> = trie
+            .iter_words_with_value::<usize>()
+            .map(|(w, &v)| (w, v))
+            .collect();
+        assert_eq!(values, expected);
+
+        for (_, value) in trie.iter_words_with_value_mut::<usize>() {
+            *value *= 2;
+        }
+        for (i, word) in words.iter().enumerate() {
+            assert_eq!(trie.get_metadata::<usize>(word), Some(&(i * 2)));
+        }
+        assert_eq!(trie.get_metadata::<&str>("other"), Some(&"not a usize"));
+        assert_eq!(trie.iter_words_with_value_mut::<usize>().count(), 10);
+    }
+
+    #[test]
+    fn test_frequency_trie_increment() {
+        let mut trie = FrequencyTrie::default();
+        let counts: Vec<usize> = "the quick brown fox the fox"
+            .split_whitespace()
+            .map(|token| trie.increment(token))
+            .collect();
+        assert_eq!(counts, vec![1, 1, 1, 1, 2, 2]);
+
+        assert_eq!(trie.frequency("the"), 2);
+        assert_eq!(trie.frequency("fox"), 2);
+        assert_eq!(trie.frequency("quick"), 1);
+        assert_eq!(trie.distinct_word_count(), 4);
+        // t-h-e, q-u-i-c-k, b-r-o-w-n, f-o-x share no prefix.
+        assert_eq!(trie.total_node_count(), 16);
+
+        assert_eq!(trie.increment("then"), 1);
+        assert_eq!(trie.distinct_word_count(), 5);
+        assert_eq!(trie.total_node_count(), 17);
     }
 }